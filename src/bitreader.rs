@@ -0,0 +1,142 @@
+//! Bit-level field extraction shared by every RTCM3 message decoder.
+
+/// Checks that bits `[start_bit, start_bit + length)` actually exist in
+/// `data` before any caller indexes into it.
+fn check_bounds(data: &[u8], start_bit: usize, length: usize) -> Result<(), String> {
+    if start_bit + length > data.len() * 8 {
+        return Err(format!(
+            "bitfield out of bounds: need bits {}..{} but only have {} bytes",
+            start_bit,
+            start_bit + length,
+            data.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Reads an unsigned, big-endian, MSB-first bitfield up to 64 bits wide.
+pub fn parse_bits_u64(data: &[u8], start_bit: usize, length: usize) -> Result<u64, String> {
+    check_bounds(data, start_bit, length)?;
+
+    let mut value: u64 = 0;
+    for i in 0..length {
+        let byte_index = (start_bit + i) / 8;
+        let bit_index = 7 - ((start_bit + i) % 8);
+        let bit = (data[byte_index] >> bit_index) & 1;
+        value = (value << 1) | bit as u64;
+    }
+    Ok(value)
+}
+
+/// Reads a two's-complement, big-endian, MSB-first bitfield up to 64 bits
+/// wide, sign-extending the top bit into a full `i64`.
+pub fn parse_bits_signed(data: &[u8], start_bit: usize, length: usize) -> Result<i64, String> {
+    let value = parse_bits_u64(data, start_bit, length)?;
+    let sign_bit = 1u64 << (length - 1);
+    // A 64-bit field already has its sign in the top bit of the `u64`.
+    Ok(if length < 64 && value & sign_bit != 0 {
+        (value as i64) - (1i64 << length)
+    } else {
+        value as i64
+    })
+}
+
+/// A cursor over a byte slice that consumes consecutive RTCM3 bitfields
+/// without callers having to track running bit offsets themselves.
+pub struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader { data, pos: 0 }
+    }
+
+    /// Reads the next `len` bits as an unsigned value and advances the
+    /// cursor, or returns an error if `len` bits aren't left in the buffer.
+    pub fn read_u(&mut self, len: usize) -> Result<u64, String> {
+        let value = parse_bits_u64(self.data, self.pos, len)?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    /// Reads the next `len` bits as a two's-complement signed value and
+    /// advances the cursor, or returns an error if `len` bits aren't left in
+    /// the buffer.
+    pub fn read_i(&mut self, len: usize) -> Result<i64, String> {
+        let value = parse_bits_signed(self.data, self.pos, len)?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    /// Advances the cursor by `len` bits without returning anything, for
+    /// reserved fields.
+    pub fn skip(&mut self, len: usize) {
+        self.pos += len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bits_signed_one_byte() {
+        assert_eq!(parse_bits_signed(&[0x7F], 0, 8).unwrap(), 127);
+        assert_eq!(parse_bits_signed(&[0x80], 0, 8).unwrap(), -128);
+        assert_eq!(parse_bits_signed(&[0xFF], 0, 8).unwrap(), -1);
+        assert_eq!(parse_bits_signed(&[0x00], 0, 8).unwrap(), 0);
+    }
+
+    #[test]
+    fn parse_bits_signed_narrow_field() {
+        // 0b0111 in the top nibble: positive, max value for a 4-bit field.
+        assert_eq!(parse_bits_signed(&[0b0111_0000], 0, 4).unwrap(), 7);
+        // 0b1000 in the top nibble: sign bit set, most negative 4-bit value.
+        assert_eq!(parse_bits_signed(&[0b1000_0000], 0, 4).unwrap(), -8);
+    }
+
+    #[test]
+    fn parse_bits_signed_crosses_byte_boundary() {
+        assert_eq!(parse_bits_signed(&[0x7F, 0xFF], 0, 16).unwrap(), 32767);
+        assert_eq!(parse_bits_signed(&[0x80, 0x00], 0, 16).unwrap(), -32768);
+        // Offset so the 10-bit field spans the byte boundary.
+        assert_eq!(parse_bits_signed(&[0b0000_1000, 0b0000_0000], 4, 10).unwrap(), -512);
+    }
+
+    #[test]
+    fn parse_bits_signed_wide_field() {
+        assert_eq!(parse_bits_signed(&[0x00, 0x00, 0x00, 0x01], 0, 32).unwrap(), 1);
+        assert_eq!(parse_bits_signed(&[0xFF, 0xFF, 0xFF, 0xFF], 0, 32).unwrap(), -1);
+        assert_eq!(parse_bits_signed(&[0x80, 0x00, 0x00, 0x00], 0, 32).unwrap(), -2_147_483_648);
+    }
+
+    #[test]
+    fn parse_bits_out_of_bounds_is_an_error() {
+        assert!(parse_bits_u64(&[0xFF; 2], 0, 17).is_err());
+        assert!(parse_bits_signed(&[0xFF; 2], 8, 9).is_err());
+    }
+
+    #[test]
+    fn bitreader_read_i_advances_cursor_and_sign_extends() {
+        let mut reader = BitReader::new(&[0xFF, 0x80]);
+        assert_eq!(reader.read_i(8).unwrap(), -1);
+        assert_eq!(reader.read_i(8).unwrap(), -128);
+    }
+
+    #[test]
+    fn bitreader_mixes_read_u_and_read_i() {
+        let mut reader = BitReader::new(&[0b1010_1111, 0b1111_0000]);
+        assert_eq!(reader.read_u(4).unwrap(), 0b1010);
+        assert_eq!(reader.read_i(4).unwrap(), -1);
+        assert_eq!(reader.read_i(8).unwrap(), -16);
+    }
+
+    #[test]
+    fn bitreader_read_past_the_end_errors_instead_of_panicking() {
+        let mut reader = BitReader::new(&[0xFFu8; 20]);
+        reader.skip(19 * 8);
+        assert!(reader.read_u(16).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Stateful, incremental RTCM3 framing so callers can feed arbitrary-sized
+//! chunks (e.g. from a socket or serial port) instead of buffering a whole
+//! file up front.
+
+use crate::RTCM3Message;
+
+/// Finds 0xD3 preambles, reads the 10-bit length, verifies the CRC24Q, and
+/// yields complete messages as enough bytes accumulate across `push` calls.
+///
+/// On a CRC failure only the single preamble byte is discarded before
+/// resyncing, so a frame that happens to contain a stray 0xD3 byte doesn't
+/// cause the next real frame to be skipped.
+#[derive(Default)]
+pub struct Rtcm3Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Rtcm3Decoder {
+    pub fn new() -> Rtcm3Decoder {
+        Rtcm3Decoder::default()
+    }
+
+    /// Appends `chunk` to the partial-frame buffer and returns every message
+    /// that could be fully framed as a result.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<RTCM3Message> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut messages = Vec::new();
+
+        loop {
+            let preamble = match self.buffer.iter().position(|&b| b == 0xD3) {
+                Some(preamble) => preamble,
+                None => {
+                    self.buffer.clear();
+                    break;
+                }
+            };
+            self.buffer.drain(0..preamble);
+
+            // Not enough buffered yet to read the length field.
+            if self.buffer.len() < 3 {
+                break;
+            }
+
+            let byte2 = self.buffer[1];
+            let byte3 = self.buffer[2];
+            let length = ((((byte2 & 0x03) as u16) << 8) | byte3 as u16) as usize;
+            let frame_len = length + 6;
+
+            // Wait for the rest of the frame to arrive.
+            if self.buffer.len() < frame_len {
+                break;
+            }
+
+            let fulldata = &self.buffer[0..frame_len];
+            let calculated_crc = crc24q_new(fulldata);
+
+            if calculated_crc != 0 {
+                // Bad checksum - discard only the preamble byte and resync.
+                self.buffer.drain(0..1);
+                continue;
+            }
+
+            let msg = self.buffer[3..length + 3].to_vec();
+            messages.push(RTCM3Message { raw: msg });
+
+            self.buffer.drain(0..frame_len);
+        }
+
+        messages
+    }
+}
+
+pub fn crc24q_new(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0;
+    let poly = 0x1864CFB;
+
+    for octet in data {
+        crc ^= (*octet as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= poly;
+            }
+        }
+    }
+
+    crc & 0xFFFFFF
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // D3 00 03 <payload> <crc24q>, a minimal well-formed frame.
+    const GOOD_FRAME: [u8; 9] = [0xD3, 0x00, 0x03, 0xAA, 0xBB, 0xCC, 0x5F, 0xBD, 0x1C];
+
+    #[test]
+    fn decodes_a_single_well_formed_frame() {
+        let mut decoder = Rtcm3Decoder::new();
+        let messages = decoder.push(&GOOD_FRAME);
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].raw, vec![0xAA, 0xBB, 0xCC]);
+    }
+
+    #[test]
+    fn resyncs_after_a_bad_crc_instead_of_dropping_the_next_frame() {
+        // Same frame as GOOD_FRAME but with its last CRC byte flipped, so
+        // the checksum no longer validates.
+        let bad_frame: [u8; 9] = [0xD3, 0x00, 0x03, 0xAA, 0xBB, 0xCC, 0x5F, 0xBD, 0xE3];
+        let good_frame: [u8; 9] = [0xD3, 0x00, 0x03, 0x11, 0x22, 0x33, 0x89, 0xA3, 0x72];
+
+        let mut combined = Vec::new();
+        combined.extend_from_slice(&bad_frame);
+        combined.extend_from_slice(&good_frame);
+
+        let mut decoder = Rtcm3Decoder::new();
+        let messages = decoder.push(&combined);
+
+        // The bad frame is discarded byte-by-byte on CRC failure, so the
+        // valid frame immediately after it is still recovered.
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].raw, vec![0x11, 0x22, 0x33]);
+    }
+
+    #[test]
+    fn buffers_a_frame_split_across_multiple_pushes() {
+        let mut decoder = Rtcm3Decoder::new();
+
+        assert!(decoder.push(&GOOD_FRAME[0..5]).is_empty());
+        let messages = decoder.push(&GOOD_FRAME[5..]);
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].raw, vec![0xAA, 0xBB, 0xCC]);
+    }
+}
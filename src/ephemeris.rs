@@ -0,0 +1,259 @@
+//! Keplerian broadcast ephemeris decoding (RTCM 1019 GPS / 1045 Galileo F/NAV).
+
+use crate::bitreader::BitReader;
+
+/// Broadcast orbital elements, in physical units, common to the GPS and
+/// Galileo F/NAV ephemeris messages.
+#[derive(Debug)]
+pub struct Ephemeris {
+    pub satellite_id: u8,
+    pub week_number: u16,
+    pub ura_index: u8,
+    pub code_on_l2: u8,
+    pub idot: f64,
+    pub iode: u16,
+    pub t_oc: f64,
+    pub a_f2: f64,
+    pub a_f1: f64,
+    pub a_f0: f64,
+    pub iodc: u16,
+    pub c_rs: f64,
+    pub delta_n: f64,
+    pub m0: f64,
+    pub c_uc: f64,
+    pub e: f64,
+    pub c_us: f64,
+    pub sqrt_a: f64,
+    pub t_oe: f64,
+    pub c_ic: f64,
+    pub omega0: f64,
+    pub c_is: f64,
+    pub i0: f64,
+    pub c_rc: f64,
+    pub omega: f64,
+    pub omega_dot: f64,
+}
+
+const PI: f64 = std::f64::consts::PI;
+
+/// Decodes the GPS (1019) ephemeris body that follows the 12-bit message
+/// number.
+pub fn extract_ephemeris_gps(reader: &mut BitReader) -> Result<Ephemeris, String> {
+    let satellite_id = reader.read_u(6)? as u8;
+    let week_number = reader.read_u(10)? as u16;
+    let ura_index = reader.read_u(4)? as u8;
+    let code_on_l2 = reader.read_u(2)? as u8;
+    let idot = reader.read_i(14)? as f64 * 2f64.powi(-43) * PI;
+    let iode = reader.read_u(8)? as u16;
+    let t_oc = reader.read_u(16)? as f64 * 2f64.powi(4);
+    let a_f2 = reader.read_i(8)? as f64 * 2f64.powi(-55);
+    let a_f1 = reader.read_i(16)? as f64 * 2f64.powi(-43);
+    let a_f0 = reader.read_i(22)? as f64 * 2f64.powi(-31);
+    let iodc = reader.read_u(10)? as u16;
+    let c_rs = reader.read_i(16)? as f64 * 2f64.powi(-5);
+    let delta_n = reader.read_i(16)? as f64 * 2f64.powi(-43) * PI;
+    let m0 = reader.read_i(32)? as f64 * 2f64.powi(-31) * PI;
+    let c_uc = reader.read_i(16)? as f64 * 2f64.powi(-29);
+    let e = reader.read_u(32)? as f64 * 2f64.powi(-33);
+    let c_us = reader.read_i(16)? as f64 * 2f64.powi(-29);
+    let sqrt_a = reader.read_u(32)? as f64 * 2f64.powi(-19);
+    let t_oe = reader.read_u(16)? as f64 * 2f64.powi(4);
+    let c_ic = reader.read_i(16)? as f64 * 2f64.powi(-29);
+    let omega0 = reader.read_i(32)? as f64 * 2f64.powi(-31) * PI;
+    let c_is = reader.read_i(16)? as f64 * 2f64.powi(-29);
+    let i0 = reader.read_i(32)? as f64 * 2f64.powi(-31) * PI;
+    let c_rc = reader.read_i(16)? as f64 * 2f64.powi(-5);
+    let omega = reader.read_i(32)? as f64 * 2f64.powi(-31) * PI;
+    let omega_dot = reader.read_i(24)? as f64 * 2f64.powi(-43) * PI;
+
+    Ok(Ephemeris {
+        satellite_id,
+        week_number,
+        ura_index,
+        code_on_l2,
+        idot,
+        iode,
+        t_oc,
+        a_f2,
+        a_f1,
+        a_f0,
+        iodc,
+        c_rs,
+        delta_n,
+        m0,
+        c_uc,
+        e,
+        c_us,
+        sqrt_a,
+        t_oe,
+        c_ic,
+        omega0,
+        c_is,
+        i0,
+        c_rc,
+        omega,
+        omega_dot,
+    })
+}
+
+/// Decodes the Galileo F/NAV (1045) ephemeris body that follows the 12-bit
+/// message number. Galileo F/NAV is *not* bit-compatible with GPS 1019 past
+/// `satellite_id` - it has a wider week number, a single IODnav in place of
+/// GPS's separate IODE/IODC, an 8-bit SISA in place of URA/code-on-L2, and
+/// narrower toc/toe/af2 but wider af0/af1 fields - so it gets its own reader
+/// sequence.
+/// IODnav is stored in both `iode` and `iodc` since Galileo has only the one
+/// issue-of-data field.
+pub fn extract_ephemeris_galileo(reader: &mut BitReader) -> Result<Ephemeris, String> {
+    let satellite_id = reader.read_u(6)? as u8;
+    let week_number = reader.read_u(12)? as u16;
+    let iodnav = reader.read_u(10)? as u16;
+    let sisa = reader.read_u(8)? as u8;
+    let idot = reader.read_i(14)? as f64 * 2f64.powi(-43) * PI;
+    let t_oc = reader.read_u(14)? as f64 * 60.0;
+    let a_f2 = reader.read_i(6)? as f64 * 2f64.powi(-59);
+    let a_f1 = reader.read_i(21)? as f64 * 2f64.powi(-46);
+    let a_f0 = reader.read_i(31)? as f64 * 2f64.powi(-34);
+    let c_rs = reader.read_i(16)? as f64 * 2f64.powi(-5);
+    let delta_n = reader.read_i(16)? as f64 * 2f64.powi(-43) * PI;
+    let m0 = reader.read_i(32)? as f64 * 2f64.powi(-31) * PI;
+    let c_uc = reader.read_i(16)? as f64 * 2f64.powi(-29);
+    let e = reader.read_u(32)? as f64 * 2f64.powi(-33);
+    let c_us = reader.read_i(16)? as f64 * 2f64.powi(-29);
+    let sqrt_a = reader.read_u(32)? as f64 * 2f64.powi(-19);
+    let t_oe = reader.read_u(14)? as f64 * 60.0;
+    let c_ic = reader.read_i(16)? as f64 * 2f64.powi(-29);
+    let omega0 = reader.read_i(32)? as f64 * 2f64.powi(-31) * PI;
+    let c_is = reader.read_i(16)? as f64 * 2f64.powi(-29);
+    let i0 = reader.read_i(32)? as f64 * 2f64.powi(-31) * PI;
+    let c_rc = reader.read_i(16)? as f64 * 2f64.powi(-5);
+    let omega = reader.read_i(32)? as f64 * 2f64.powi(-31) * PI;
+    let omega_dot = reader.read_i(24)? as f64 * 2f64.powi(-43) * PI;
+
+    Ok(Ephemeris {
+        satellite_id,
+        week_number,
+        ura_index: sisa,
+        code_on_l2: 0,
+        idot,
+        iode: iodnav,
+        t_oc,
+        a_f2,
+        a_f1,
+        a_f0,
+        iodc: iodnav,
+        c_rs,
+        delta_n,
+        m0,
+        c_uc,
+        e,
+        c_us,
+        sqrt_a,
+        t_oe,
+        c_ic,
+        omega0,
+        c_is,
+        i0,
+        c_rc,
+        omega,
+        omega_dot,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-9 + 1e-12,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    // Hand-packed GPS 1019 body (the 12-bit message number that precedes
+    // this in a real frame is read separately by main.rs, so it isn't part
+    // of this buffer).
+    const GPS_1019_BODY: [u8; 58] = [
+        0x30, 0xAE, 0x54, 0x06, 0x42, 0xDF, 0x00, 0x0F, 0xD0, 0x0C, 0x8F, 0xFB, 0x1E, 0x04, 0xD0,
+        0x12, 0xCF, 0xE7, 0x00, 0x75, 0xBC, 0xD1, 0x5F, 0xF9, 0x13, 0xAD, 0xE6, 0x8B, 0x10, 0x0D,
+        0xE7, 0x73, 0x59, 0x40, 0x0C, 0x35, 0x0F, 0xEB, 0x3F, 0x2C, 0x12, 0x87, 0x20, 0x1B, 0xC1,
+        0x3D, 0xE4, 0x35, 0x5F, 0xDD, 0x5E, 0x58, 0x25, 0x0E, 0x4F, 0xFE, 0x0C, 0x00,
+    ];
+
+    #[test]
+    fn extract_ephemeris_gps_decodes_a_golden_frame() {
+        let mut reader = BitReader::new(&GPS_1019_BODY);
+        let e = extract_ephemeris_gps(&mut reader).unwrap();
+
+        assert_eq!(e.satellite_id, 12);
+        assert_eq!(e.week_number, 174); // 10-bit field: week 2222 modulo 1024.
+        assert_eq!(e.ura_index, 5);
+        assert_eq!(e.code_on_l2, 1);
+        assert_eq!(e.iode, 45);
+        assert_eq!(e.iodc, 77);
+        assert_close(e.idot, 3.571577341960839e-11);
+        assert_close(e.t_oc, 983040.0);
+        assert_close(e.a_f2, -8.326672684688674e-17);
+        assert_close(e.a_f1, 2.2737367544323206e-11);
+        assert_close(e.a_f0, -2.3283064365386963e-6);
+        assert_close(e.c_rs, 9.375);
+        assert_close(e.delta_n, -1.4286309367843356e-10);
+        assert_close(e.m0, 0.180607168636371);
+        assert_close(e.c_uc, -2.0675361156463623e-7);
+        assert_close(e.e, 0.11497809563297778);
+        assert_close(e.c_us, 4.1350722312927246e-7);
+        assert_close(e.sqrt_a, 3814.697265625);
+        assert_close(e.t_oe, 800000.0);
+        assert_close(e.c_ic, -6.202608346939087e-7);
+        assert_close(e.omega0, -0.32509290617872033);
+        assert_close(e.c_is, 8.270144462585449e-7);
+        assert_close(e.i0, 0.4876393592680805);
+        assert_close(e.c_rc, -17.34375);
+        assert_close(e.omega, -0.6501858123574407);
+        assert_close(e.omega_dot, -2.857261873568671e-9);
+    }
+
+    // Hand-packed Galileo F/NAV 1045 body, same convention as above.
+    const GALILEO_1045_BODY: [u8; 58] = [
+        0x1D, 0x15, 0xC5, 0x80, 0xF0, 0x32, 0x23, 0x28, 0xD8, 0x02, 0x71, 0x1F, 0xFF, 0xBB, 0xA4,
+        0x00, 0x25, 0xBF, 0xC1, 0x80, 0xBE, 0xBC, 0x20, 0x00, 0x18, 0xC6, 0x9F, 0x6B, 0xC7, 0x3F,
+        0xEA, 0x16, 0x5A, 0x0B, 0xC0, 0x17, 0x70, 0x00, 0x4D, 0xFA, 0x0A, 0x1F, 0x01, 0xFF, 0xBE,
+        0x05, 0x4C, 0x56, 0x38, 0x00, 0x7B, 0xFB, 0x5D, 0x34, 0x8F, 0x00, 0x0F, 0xA0,
+    ];
+
+    #[test]
+    fn extract_ephemeris_galileo_decodes_a_golden_frame() {
+        let mut reader = BitReader::new(&GALILEO_1045_BODY);
+        let e = extract_ephemeris_galileo(&mut reader).unwrap();
+
+        assert_eq!(e.satellite_id, 7);
+        assert_eq!(e.week_number, 1111);
+        assert_eq!(e.iode, 88); // IODnav, stored in `iode`.
+        assert_eq!(e.iodc, 88); // IODnav, also stored in `iodc`.
+        assert_eq!(e.ura_index, 15); // SISA, stored in `ura_index`.
+        assert_eq!(e.code_on_l2, 0); // Galileo has no code-on-L2 field.
+        assert_close(e.idot, 7.143154683921678e-11);
+        assert_close(e.t_oc, 540000.0);
+        assert_close(e.a_f2, -1.734723475976807e-17);
+        assert_close(e.a_f1, 7.105427357601002e-11);
+        assert_close(e.a_f0, -4.0745362639427185e-6);
+        assert_close(e.c_rs, 4.6875);
+        assert_close(e.delta_n, -8.928943354902097e-11);
+        assert_close(e.m0, 0.07314590396335797);
+        assert_close(e.c_uc, 1.8440186977386475e-7);
+        assert_close(e.e, 0.05174014298245311);
+        assert_close(e.c_us, -1.6391277313232422e-7);
+        assert_close(e.sqrt_a, 2861.02294921875);
+        assert_close(e.t_oe, 360000.0);
+        assert_close(e.c_ic, 1.434236764907837e-7);
+        assert_close(e.omega0, -0.14629180646379789);
+        assert_close(e.c_is, -1.2293457984924316e-7);
+        assert_close(e.i0, 0.13003716130115367);
+        assert_close(e.c_rc, 3.84375);
+        assert_close(e.omega, -0.11378251613850947);
+        assert_close(e.omega_dot, 1.4286309367843355e-9);
+    }
+}
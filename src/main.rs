@@ -1,11 +1,23 @@
 use std::{fs::File, io::Read};
 
-struct RTCM3Message {
-    raw: Vec<u8>
+mod bitreader;
+mod decoder;
+mod ephemeris;
+mod ntrip;
+mod output;
+
+use bitreader::BitReader;
+use decoder::Rtcm3Decoder;
+use ephemeris::Ephemeris;
+use ntrip::{NtripSource, NtripUrl};
+use output::OutputFormat;
+
+pub(crate) struct RTCM3Message {
+    pub(crate) raw: Vec<u8>
 }
 
 #[derive(strum_macros::Display)]
-enum MessageType {
+pub(crate) enum MessageType {
     #[strum(to_string = "Unknown<value: {val}>")]
     Unknown { val: u16 },
 
@@ -42,17 +54,63 @@ enum MessageType {
 }
 
 #[derive(strum_macros::Display)]
-enum MessageInformation {
-    #[strum(to_string = "MSM7<Num:{message_number},RefStnId:{reference_station_id},Epch:{epoch_time}>")]
+pub(crate) enum MessageInformation {
+    #[strum(to_string = "MSM7<Num:{message_number},RefStnId:{reference_station_id},Epch:{epoch_time},Sats:{num_satellites},Cells:{num_cells}>")]
     MSM7 {
         message_number: u16,
         reference_station_id: u16,
-        epoch_time: u32
+        epoch_time: u32,
+        multiple_message_flag: bool,
+        iods: u8,
+        clock_steering_indicator: u8,
+        external_clock_indicator: u8,
+        divergence_free_smoothing: bool,
+        smoothing_interval: u8,
+        satellite_mask: u64,
+        signal_mask: u32,
+        cell_mask: Vec<bool>,
+        num_satellites: usize,
+        num_cells: usize,
+        satellites: Vec<MSM7Satellite>,
+        signals: Vec<MSM7Signal>,
+    },
+    #[strum(to_string = "Ephemeris<Num:{message_number}>")]
+    Ephemeris {
+        message_number: u16,
+        ephemeris: Ephemeris,
+    },
+    #[strum(to_string = "StationInfo<RefStnId:{reference_station_id}>")]
+    StationInfo {
+        reference_station_id: u16,
+        ecef_x: f64,
+        ecef_y: f64,
+        ecef_z: f64,
+        antenna_height: f64,
     },
     Unknown
 }
 
-trait Message {
+/// Per-satellite fields of an MSM7 satellite data block.
+#[derive(Debug)]
+pub(crate) struct MSM7Satellite {
+    pub(crate) rough_range_ms: u8,
+    pub(crate) extended_info: u8,
+    pub(crate) rough_range_modulo_ms: u16,
+    pub(crate) rough_phase_range_rate: i16,
+}
+
+/// Per-cell (satellite/signal pair) fields of an MSM7 signal data block.
+#[derive(Debug)]
+pub(crate) struct MSM7Signal {
+    pub(crate) fine_pseudorange: i32,
+    pub(crate) fine_phase_range: i32,
+    pub(crate) lock_time_indicator: u16,
+    pub(crate) half_cycle_ambiguity: bool,
+    pub(crate) cnr: u16,
+    pub(crate) fine_phase_range_rate: i16,
+}
+
+pub(crate) trait Message {
     fn get_type(&self) -> MessageType;
     fn get_information(&self) -> Result<MessageInformation, String>;
 }
@@ -88,135 +146,299 @@ impl Message for RTCM3Message {
     
     fn get_information(&self) -> Result<MessageInformation, String> {
         match self.get_type() {
-            MessageType::BeiDouMSM7 => extract_msm7(&self.raw),
+            MessageType::GPSMSM7
+            | MessageType::GLONASSMSM7
+            | MessageType::GalileoMSM7
+            | MessageType::BeiDouMSM7
+            | MessageType::QZSSMSM7 => extract_msm7(&self.raw),
+            MessageType::GPSEphemerides => extract_ephemeris_gps(&self.raw),
+            MessageType::GalileoFNAVSatelliteEphemeris => extract_ephemeris_galileo(&self.raw),
+            MessageType::StationaryRTKReferenceStationARPWithAntennaHeight => {
+                extract_station_info(&self.raw)
+            }
             _ => Ok(MessageInformation::Unknown)
         }
     }
 }
 
-fn extract_msm7(raw: &Vec<u8>) -> Result<MessageInformation, String> {
-
-    // TODO add the rest of the spec.
-    return Ok(MessageInformation::MSM7 {
-        message_number: parse_bits(raw, 0, 12) as u16,
-        reference_station_id: parse_bits(raw, 12, 12) as u16,
-        epoch_time: parse_bits(raw, 24, 30)
-    });
+fn extract_station_info(raw: &Vec<u8>) -> Result<MessageInformation, String> {
+    let mut reader = BitReader::new(raw);
+
+    reader.skip(12); // Message number.
+    let reference_station_id = reader.read_u(12)? as u16;
+    reader.skip(6); // ITRF realization year.
+    reader.skip(1); // GPS indicator.
+    reader.skip(1); // GLONASS indicator.
+    reader.skip(1); // Reserved for Galileo.
+    reader.skip(1); // Reference-station indicator.
+    let ecef_x = reader.read_i(38)? as f64 * 0.0001;
+    reader.skip(1); // Single receiver oscillator indicator.
+    reader.skip(1); // Reserved.
+    let ecef_y = reader.read_i(38)? as f64 * 0.0001;
+    reader.skip(2); // Quarter cycle indicator.
+    let ecef_z = reader.read_i(38)? as f64 * 0.0001;
+    let antenna_height = reader.read_u(16)? as f64 * 0.0001;
+
+    Ok(MessageInformation::StationInfo {
+        reference_station_id,
+        ecef_x,
+        ecef_y,
+        ecef_z,
+        antenna_height,
+    })
 }
 
-impl ToString for RTCM3Message {
-    fn to_string(&self) -> String {
-        match self.get_type() {
-            _ => "Unknown".to_string()
-        }
-    }
-}
+fn extract_ephemeris_gps(raw: &Vec<u8>) -> Result<MessageInformation, String> {
+    let mut reader = BitReader::new(raw);
 
-fn parse_bits(data: &[u8], start_bit: usize, length: usize) -> u32 {
-    let mut value: u32 = 0;
-    for i in 0..length {
-        let byte_index = (start_bit + i) / 8;
-        let bit_index = 7 - ((start_bit + i) % 8);
-        let bit = (data[byte_index] >> bit_index) & 1;
-        value = (value << 1) | bit as u32;
-    }
-    value
+    let message_number = reader.read_u(12)? as u16;
+    let ephemeris = ephemeris::extract_ephemeris_gps(&mut reader)?;
+
+    Ok(MessageInformation::Ephemeris {
+        message_number,
+        ephemeris,
+    })
 }
 
-fn main() {
-    let messages = parse_rtcm3().unwrap();
+fn extract_ephemeris_galileo(raw: &Vec<u8>) -> Result<MessageInformation, String> {
+    let mut reader = BitReader::new(raw);
 
-    for msg in messages {
-        let info = msg.get_information().unwrap();
-        println!("{}", info);
-    }
-}
+    let message_number = reader.read_u(12)? as u16;
+    let ephemeris = ephemeris::extract_ephemeris_galileo(&mut reader)?;
 
-fn parse_rtcm3() -> Result<Vec<RTCM3Message>, String> {
-    let mut f = File::open("sample_data_2")
-        .map_err(|_| "Could not open file")?;
+    Ok(MessageInformation::Ephemeris {
+        message_number,
+        ephemeris,
+    })
+}
 
-    let mut buffer = Vec::new();
-    f.read_to_end(&mut buffer).map_err(|_| "Error reading file")?;
+fn extract_msm7(raw: &Vec<u8>) -> Result<MessageInformation, String> {
+    let mut reader = BitReader::new(raw);
+
+    let message_number = reader.read_u(12)? as u16;
+    let reference_station_id = reader.read_u(12)? as u16;
+    let epoch_time = reader.read_u(30)? as u32;
+
+    let multiple_message_flag = reader.read_u(1)? == 1;
+    let iods = reader.read_u(3)? as u8;
+    reader.skip(7); // Reserved.
+    let clock_steering_indicator = reader.read_u(2)? as u8;
+    let external_clock_indicator = reader.read_u(2)? as u8;
+    let divergence_free_smoothing = reader.read_u(1)? == 1;
+    let smoothing_interval = reader.read_u(3)? as u8;
+
+    let satellite_mask = reader.read_u(64)?;
+    let signal_mask = reader.read_u(32)? as u32;
+
+    let num_satellites = satellite_mask.count_ones() as usize;
+    let num_signals = signal_mask.count_ones() as usize;
+    let num_cells_bits = num_satellites * num_signals;
+
+    let mut cell_mask = Vec::with_capacity(num_cells_bits);
+    for _ in 0..num_cells_bits {
+        cell_mask.push(reader.read_u(1)? == 1);
+    }
+    let num_cells = cell_mask.iter().filter(|&&set| set).count();
+
+    let mut satellites = Vec::with_capacity(num_satellites);
+    for _ in 0..num_satellites {
+        satellites.push(MSM7Satellite {
+            rough_range_ms: reader.read_u(8)? as u8,
+            extended_info: reader.read_u(4)? as u8,
+            rough_range_modulo_ms: reader.read_u(10)? as u16,
+            rough_phase_range_rate: reader.read_i(14)? as i16,
+        });
+    }
 
-    let mut messages = Vec::new();
+    let mut signals = Vec::with_capacity(num_cells);
+    for _ in 0..num_cells {
+        signals.push(MSM7Signal {
+            fine_pseudorange: reader.read_i(20)? as i32,
+            fine_phase_range: reader.read_i(24)? as i32,
+            lock_time_indicator: reader.read_u(10)? as u16,
+            half_cycle_ambiguity: reader.read_u(1)? == 1,
+            cnr: reader.read_u(10)? as u16,
+            fine_phase_range_rate: reader.read_i(15)? as i16,
+        });
+    }
 
-    let mut offset = 0;
-    while offset < buffer.len() {
-        let data = &buffer[offset..];
-        let byte1 = data[0];
+    Ok(MessageInformation::MSM7 {
+        message_number,
+        reference_station_id,
+        epoch_time,
+        multiple_message_flag,
+        iods,
+        clock_steering_indicator,
+        external_clock_indicator,
+        divergence_free_smoothing,
+        smoothing_interval,
+        satellite_mask,
+        signal_mask,
+        cell_mask,
+        num_satellites,
+        num_cells,
+        satellites,
+        signals,
+    })
+}
 
-        // Check if this is a RTCM3 start byte marker, skip if not.
-        if byte1 != 0xD3 {
-            offset += 1;
-            continue;
+fn main() {
+    let mut source = None;
+    let mut format_name = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--format" {
+            format_name = args.next();
+        } else {
+            source = Some(arg);
         }
+    }
 
-        // Then combine the next two bytes.
-        // The first six bits are zero reserved, but the last 10 are the length
-        // of the frame.
-        // this makes 16 in total, so we just assume the first six are zero.
-        let byte2 = data[1];
-        let byte3 = data[2];
-        let length = (((byte2 as u16) << 8) | byte3 as u16) as usize;
-
-        // Ignore incomplete end of file frames.
-        if data.len() < length + 6 {
-            offset += 1;
-            continue;
+    let source = source.unwrap_or_else(|| "sample_data_2".to_string());
+    let format = OutputFormat::parse(format_name.as_deref().unwrap_or("json")).unwrap();
+
+    let mut reader: Box<dyn Read> = if let Some(url) = source.strip_prefix("ntrip://").map(|_| source.as_str()) {
+        let url = NtripUrl::parse(url).unwrap();
+        Box::new(NtripSource::connect(&url).unwrap())
+    } else {
+        Box::new(File::open(&source).unwrap())
+    };
+
+    let mut stdout = std::io::stdout();
+
+    match &format {
+        // JSON lines are self-delimiting, so each chunk's messages can be
+        // emitted as soon as they're decoded.
+        OutputFormat::Json => {
+            parse_rtcm3(&mut reader, |messages| {
+                output::write_jsonl(&messages, &mut stdout)
+            })
+            .unwrap();
         }
-
-        // Get the CRC info and calculate the crc.
-        // It is good if the calculated CRC is zero.
-        let crc = &data[length + 3..length + 6];
-        let fulldata = &data[0..length + 6];
-        let calculated_crc = crc24q_new(fulldata);
-
-        // Bad checksum - skip.
-        if calculated_crc != 0 {
-            offset += 1;
-            continue;
+        // The RINEX writer needs the whole message set up front (it derives
+        // the header from station-info/MSM7 messages seen anywhere in the
+        // stream), so accumulate until the source is exhausted.
+        OutputFormat::Rinex => {
+            let mut messages = Vec::new();
+            parse_rtcm3(&mut reader, |batch| {
+                messages.extend(batch);
+                Ok(())
+            })
+            .unwrap();
+            output::write_messages(&messages, format, &mut stdout).unwrap();
         }
+    }
+}
 
-        // Now read the actual message.
-        let msg = &data[3..length + 3];
-
-        messages.push(RTCM3Message {
-            raw: msg.to_vec()
-        });
-
-        // The type is the first 12 bits. So take the first byte (8 bits) and the last 4 bits of the second byte.
-        //let msgtype = (msg[0] as u16) << 4 | (msg[1] as u16) >> 4;
-
-        println!("Frame - length: {} - crc: {:#x} {:#x} {:#x} - calculated crc: {}", length, crc[0], crc[1], crc[2], calculated_crc);
+/// Reads `reader` in bounded chunks and feeds each one to a `Rtcm3Decoder`,
+/// calling `on_messages` with whatever messages that chunk completed.
+///
+/// This is deliberately a loop over bounded `read` calls rather than
+/// `read_to_end`: a live NTRIP mountpoint never sends EOF, so buffering the
+/// whole source up front would block forever and never emit anything.
+fn parse_rtcm3<R: Read>(
+    reader: &mut R,
+    mut on_messages: impl FnMut(Vec<RTCM3Message>) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut decoder = Rtcm3Decoder::new();
+    let mut chunk = [0u8; 4096];
+
+    loop {
+        let read = reader.read(&mut chunk).map_err(|e| format!("Error reading source: {}", e))?;
+        if read == 0 {
+            break;
+        }
 
-        // Move the offset forward.
-        // The total length of the frame is:
-        // 1 byte - header
-        // 2 bytes - length info
-        // n bytes - the length of the frame
-        // 4 bytes - type + crc
-        offset += length as usize + 7;
+        let messages = decoder.push(&chunk[..read]);
+        if !messages.is_empty() {
+            on_messages(messages)?;
+        }
     }
 
-    println!("Done!");
-
-    Ok(messages)
+    Ok(())
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A single-satellite, single-signal MSM7 body (one cell mask bit set),
+    // hand-packed field by field: message number 1077, ref station 5, epoch
+    // 123456ms, one satellite/signal block with known rough/fine values.
+    const MSM7_ONE_CELL: [u8; 36] = [
+        0x43, 0x50, 0x05, 0x00, 0x07, 0x89, 0x00, 0x80, 0x26, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x59, 0x0E, 0x00, 0xFE, 0x70, 0x0C, 0x0E, 0x7F, 0xF9,
+        0x5E, 0xDF, 0x4E, 0x41, 0xFC, 0xE0,
+    ];
+
+    #[test]
+    fn extract_msm7_decodes_header_and_masks() {
+        let info = extract_msm7(&MSM7_ONE_CELL.to_vec()).unwrap();
+
+        match info {
+            MessageInformation::MSM7 {
+                message_number,
+                reference_station_id,
+                epoch_time,
+                multiple_message_flag,
+                iods,
+                clock_steering_indicator,
+                external_clock_indicator,
+                divergence_free_smoothing,
+                smoothing_interval,
+                satellite_mask,
+                signal_mask,
+                num_satellites,
+                num_cells,
+                ..
+            } => {
+                assert_eq!(message_number, 1077);
+                assert_eq!(reference_station_id, 5);
+                assert_eq!(epoch_time, 123456);
+                assert!(!multiple_message_flag);
+                assert_eq!(iods, 2);
+                assert_eq!(clock_steering_indicator, 1);
+                assert_eq!(external_clock_indicator, 0);
+                assert!(divergence_free_smoothing);
+                assert_eq!(smoothing_interval, 5);
+                assert_eq!(satellite_mask, 1u64 << 63);
+                assert_eq!(signal_mask, 1u32 << 31);
+                assert_eq!(num_satellites, 1);
+                assert_eq!(num_cells, 1);
+            }
+            _ => panic!("expected MSM7 message information"),
+        }
+    }
 
-fn crc24q_new(data: &[u8]) -> u32 {
-    let mut crc: u32 = 0;
-    let poly = 0x1864CFB;
-
-    for octet in data {
-        crc ^= (*octet as u32) << 16;
-        for _ in 0..8 {
-            crc <<= 1;
-            if crc & 0x1000000 != 0 {
-                crc ^= poly;
+    #[test]
+    fn extract_msm7_decodes_satellite_and_signal_blocks() {
+        let info = extract_msm7(&MSM7_ONE_CELL.to_vec()).unwrap();
+
+        match info {
+            MessageInformation::MSM7 { satellites, signals, .. } => {
+                assert_eq!(satellites.len(), 1);
+                assert_eq!(satellites[0].rough_range_ms, 100);
+                assert_eq!(satellites[0].extended_info, 3);
+                assert_eq!(satellites[0].rough_range_modulo_ms, 512);
+                assert_eq!(satellites[0].rough_phase_range_rate, -100);
+
+                assert_eq!(signals.len(), 1);
+                assert_eq!(signals[0].fine_pseudorange, 12345);
+                assert_eq!(signals[0].fine_phase_range, -6789);
+                assert_eq!(signals[0].lock_time_indicator, 500);
+                assert!(signals[0].half_cycle_ambiguity);
+                assert_eq!(signals[0].cnr, 800);
+                assert_eq!(signals[0].fine_phase_range_rate, -200);
             }
+            _ => panic!("expected MSM7 message information"),
         }
     }
 
-    return crc & 0xFFFFFF;
-}
\ No newline at end of file
+    #[test]
+    fn extract_msm7_errors_instead_of_panicking_on_truncated_input() {
+        // All-ones masks claim 64 satellites and 32 signals, far more than
+        // 20 bytes can possibly contain - this must surface as an `Err`,
+        // not index out of bounds.
+        assert!(extract_msm7(&vec![0xFFu8; 20]).is_err());
+    }
+}
@@ -0,0 +1,394 @@
+//! Output serializers for decoded RTCM3 messages: a RINEX v3 observation
+//! writer and a line-delimited JSON emitter.
+
+use std::io::Write;
+
+use crate::{Message, MessageInformation, MessageType, MSM7Satellite, MSM7Signal, RTCM3Message};
+
+pub(crate) enum OutputFormat {
+    Rinex,
+    Json,
+}
+
+impl OutputFormat {
+    pub(crate) fn parse(name: &str) -> Result<OutputFormat, String> {
+        match name {
+            "rinex" => Ok(OutputFormat::Rinex),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(format!("Unknown output format '{}', expected rinex or json", other)),
+        }
+    }
+}
+
+pub(crate) fn write_messages(
+    messages: &[RTCM3Message],
+    format: OutputFormat,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    match format {
+        OutputFormat::Json => write_jsonl(messages, out),
+        OutputFormat::Rinex => write_rinex(messages, out),
+    }
+}
+
+pub(crate) fn write_jsonl(messages: &[RTCM3Message], out: &mut dyn Write) -> Result<(), String> {
+    for msg in messages {
+        let info = msg.get_information()?;
+        let line = format!(
+            "{{\"type\":\"{}\",\"fields\":{}}}",
+            json_escape(&msg.get_type().to_string()),
+            info.to_json()
+        );
+        writeln!(out, "{}", line).map_err(|e| format!("Error writing JSON line: {}", e))?;
+    }
+
+    Ok(())
+}
+
+fn json_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl MessageInformation {
+    /// Renders the decoded fields as a JSON object body (no surrounding
+    /// `{"type": ...}` wrapper - see `write_jsonl`).
+    pub(crate) fn to_json(&self) -> String {
+        match self {
+            MessageInformation::MSM7 {
+                message_number,
+                reference_station_id,
+                epoch_time,
+                multiple_message_flag,
+                iods,
+                clock_steering_indicator,
+                external_clock_indicator,
+                divergence_free_smoothing,
+                smoothing_interval,
+                satellite_mask,
+                signal_mask,
+                cell_mask,
+                num_satellites,
+                num_cells,
+                satellites,
+                signals,
+            } => {
+                let cell_mask_json: Vec<&str> = cell_mask.iter().map(|&set| if set { "true" } else { "false" }).collect();
+
+                let satellites_json: Vec<String> = satellites
+                    .iter()
+                    .map(|s| {
+                        format!(
+                            "{{\"rough_range_ms\":{},\"extended_info\":{},\"rough_range_modulo_ms\":{},\"rough_phase_range_rate\":{}}}",
+                            s.rough_range_ms, s.extended_info, s.rough_range_modulo_ms, s.rough_phase_range_rate
+                        )
+                    })
+                    .collect();
+
+                let signals_json: Vec<String> = signals
+                    .iter()
+                    .map(|s| {
+                        format!(
+                            "{{\"fine_pseudorange\":{},\"fine_phase_range\":{},\"lock_time_indicator\":{},\"half_cycle_ambiguity\":{},\"cnr\":{},\"fine_phase_range_rate\":{}}}",
+                            s.fine_pseudorange, s.fine_phase_range, s.lock_time_indicator, s.half_cycle_ambiguity, s.cnr, s.fine_phase_range_rate
+                        )
+                    })
+                    .collect();
+
+                format!(
+                    "{{\"message_number\":{},\"reference_station_id\":{},\"epoch_time\":{},\"multiple_message_flag\":{},\"iods\":{},\"clock_steering_indicator\":{},\"external_clock_indicator\":{},\"divergence_free_smoothing\":{},\"smoothing_interval\":{},\"satellite_mask\":{},\"signal_mask\":{},\"cell_mask\":[{}],\"num_satellites\":{},\"num_cells\":{},\"satellites\":[{}],\"signals\":[{}]}}",
+                    message_number,
+                    reference_station_id,
+                    epoch_time,
+                    multiple_message_flag,
+                    iods,
+                    clock_steering_indicator,
+                    external_clock_indicator,
+                    divergence_free_smoothing,
+                    smoothing_interval,
+                    satellite_mask,
+                    signal_mask,
+                    cell_mask_json.join(","),
+                    num_satellites,
+                    num_cells,
+                    satellites_json.join(","),
+                    signals_json.join(","),
+                )
+            }
+            MessageInformation::Ephemeris { message_number, ephemeris } => format!(
+                "{{\"message_number\":{},\"satellite_id\":{},\"week_number\":{},\"ura_index\":{},\"code_on_l2\":{},\"idot\":{},\"iode\":{},\"t_oc\":{},\"a_f2\":{},\"a_f1\":{},\"a_f0\":{},\"iodc\":{},\"c_rs\":{},\"delta_n\":{},\"m0\":{},\"c_uc\":{},\"e\":{},\"c_us\":{},\"sqrt_a\":{},\"t_oe\":{},\"c_ic\":{},\"omega0\":{},\"c_is\":{},\"i0\":{},\"c_rc\":{},\"omega\":{},\"omega_dot\":{}}}",
+                message_number,
+                ephemeris.satellite_id,
+                ephemeris.week_number,
+                ephemeris.ura_index,
+                ephemeris.code_on_l2,
+                ephemeris.idot,
+                ephemeris.iode,
+                ephemeris.t_oc,
+                ephemeris.a_f2,
+                ephemeris.a_f1,
+                ephemeris.a_f0,
+                ephemeris.iodc,
+                ephemeris.c_rs,
+                ephemeris.delta_n,
+                ephemeris.m0,
+                ephemeris.c_uc,
+                ephemeris.e,
+                ephemeris.c_us,
+                ephemeris.sqrt_a,
+                ephemeris.t_oe,
+                ephemeris.c_ic,
+                ephemeris.omega0,
+                ephemeris.c_is,
+                ephemeris.i0,
+                ephemeris.c_rc,
+                ephemeris.omega,
+                ephemeris.omega_dot,
+            ),
+            MessageInformation::StationInfo {
+                reference_station_id,
+                ecef_x,
+                ecef_y,
+                ecef_z,
+                antenna_height,
+            } => format!(
+                "{{\"reference_station_id\":{},\"ecef_x\":{},\"ecef_y\":{},\"ecef_z\":{},\"antenna_height\":{}}}",
+                reference_station_id, ecef_x, ecef_y, ecef_z, antenna_height
+            ),
+            MessageInformation::Unknown => "null".to_string(),
+        }
+    }
+}
+
+/// Speed of light, in metres per millisecond, used to turn MSM rough/fine
+/// range fields (which are expressed in milliseconds) into metres.
+const SPEED_OF_LIGHT_M_PER_MS: f64 = 299_792.458;
+
+/// Speed of light, in metres per second.
+const SPEED_OF_LIGHT_M_PER_S: f64 = SPEED_OF_LIGHT_M_PER_MS * 1000.0;
+
+/// GPS L1 / QZSS L1 / Galileo E1 carrier wavelength, in metres - all three
+/// sit in the same 1575.42 MHz band, unlike GLONASS (FDMA) or BeiDou B1I.
+const GPS_L1_WAVELENGTH_M: f64 = 0.190_293_672_798;
+
+/// BeiDou B1I carrier frequency, in Hz.
+const BEIDOU_B1I_FREQUENCY_HZ: f64 = 1_561_098_000.0;
+
+/// GLONASS L1 FDMA base carrier frequency (channel 0), in Hz.
+const GLONASS_L1_BASE_FREQUENCY_HZ: f64 = 1_602_000_000.0;
+
+/// GLONASS L1 FDMA channel spacing, in Hz.
+const GLONASS_L1_CHANNEL_SPACING_HZ: f64 = 562_500.0;
+
+/// RINEX constellation letter for the MSM7 message types, per RINEX v3.
+fn constellation_letter(message_type: &MessageType) -> Option<char> {
+    match message_type {
+        MessageType::GPSMSM7 => Some('G'),
+        MessageType::GLONASSMSM7 => Some('R'),
+        MessageType::GalileoMSM7 => Some('E'),
+        MessageType::BeiDouMSM7 => Some('C'),
+        MessageType::QZSSMSM7 => Some('J'),
+        _ => None,
+    }
+}
+
+/// Carrier wavelength (metres) for the L1-band signal of `letter`'s
+/// constellation, used to convert a decoded phase range into cycles.
+///
+/// GPS, QZSS and Galileo all broadcast L1/E1 at 1575.42 MHz, so they share
+/// `GPS_L1_WAVELENGTH_M`. BeiDou B1I and GLONASS are at materially different
+/// frequencies - GLONASS is FDMA, so its frequency also depends on the
+/// per-satellite channel number carried in `MSM7Satellite::extended_info`
+/// (RTCM DF384, offset by 7).
+fn carrier_wavelength_m(letter: char, satellite: &MSM7Satellite) -> Option<f64> {
+    match letter {
+        'G' | 'J' | 'E' => Some(GPS_L1_WAVELENGTH_M),
+        'C' => Some(SPEED_OF_LIGHT_M_PER_S / BEIDOU_B1I_FREQUENCY_HZ),
+        'R' => {
+            let channel = satellite.extended_info as i32 - 7;
+            let frequency_hz =
+                GLONASS_L1_BASE_FREQUENCY_HZ + channel as f64 * GLONASS_L1_CHANNEL_SPACING_HZ;
+            Some(SPEED_OF_LIGHT_M_PER_S / frequency_hz)
+        }
+        _ => None,
+    }
+}
+
+/// One decoded MSM7 epoch, ready to be turned into a RINEX OBS record.
+struct RinexEpoch {
+    epoch_time: u32,
+    // (constellation, prn, pseudorange_m, phase_cycles, cnr_dbhz)
+    observations: Vec<(char, u8, f64, f64, f64)>,
+}
+
+fn write_rinex(messages: &[RTCM3Message], out: &mut dyn Write) -> Result<(), String> {
+    let mut approx_position = (0.0_f64, 0.0_f64, 0.0_f64);
+    let mut reference_station_id = 0u16;
+    let mut constellations_seen = Vec::new();
+    let mut epochs: Vec<RinexEpoch> = Vec::new();
+
+    for msg in messages {
+        let message_type = msg.get_type();
+        let info = msg.get_information()?;
+
+        match &info {
+            MessageInformation::StationInfo {
+                reference_station_id: id,
+                ecef_x,
+                ecef_y,
+                ecef_z,
+                ..
+            } => {
+                reference_station_id = *id;
+                approx_position = (*ecef_x, *ecef_y, *ecef_z);
+            }
+            MessageInformation::MSM7 {
+                epoch_time,
+                num_satellites,
+                ..
+            } => {
+                if let Some(letter) = constellation_letter(&message_type) {
+                    if !constellations_seen.contains(&letter) {
+                        constellations_seen.push(letter);
+                    }
+
+                    let observations = msm7_epoch_observations(&info, letter, *num_satellites);
+                    epochs.push(RinexEpoch {
+                        epoch_time: *epoch_time,
+                        observations,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    writeln!(out, "     3.04           OBSERVATION DATA    M (MIXED)           RINEX VERSION / TYPE")
+        .map_err(|e| format!("Error writing RINEX header: {}", e))?;
+    writeln!(out, "rtk                                                         PGM / RUN BY / DATE")
+        .map_err(|e| format!("Error writing RINEX header: {}", e))?;
+    writeln!(out, "{:<60}MARKER NAME", format!("STN{:05}", reference_station_id))
+        .map_err(|e| format!("Error writing RINEX header: {}", e))?;
+    writeln!(
+        out,
+        "{:<14.4}{:<14.4}{:<14.4}                  APPROX POSITION XYZ",
+        approx_position.0, approx_position.1, approx_position.2
+    )
+    .map_err(|e| format!("Error writing RINEX header: {}", e))?;
+
+    for letter in &constellations_seen {
+        writeln!(out, "{}    3 C1C L1C S1C                                          SYS / # / OBS TYPES", letter)
+            .map_err(|e| format!("Error writing RINEX header: {}", e))?;
+    }
+
+    writeln!(out, "                                                            END OF HEADER")
+        .map_err(|e| format!("Error writing RINEX header: {}", e))?;
+
+    for epoch in &epochs {
+        // MSM epoch time is GNSS time-of-week in milliseconds; without a
+        // paired week number we can only recover time-of-day, so the date
+        // fields below are a fixed placeholder.
+        let seconds_of_day = (epoch.epoch_time / 1000) % 86400;
+        let hours = seconds_of_day / 3600;
+        let minutes = (seconds_of_day % 3600) / 60;
+        let seconds = seconds_of_day % 60;
+        let millis = epoch.epoch_time % 1000;
+
+        writeln!(
+            out,
+            "> 2026 01 01 {:02} {:02} {:02}.{:07}  0 {:2}",
+            hours,
+            minutes,
+            seconds,
+            millis * 10000,
+            epoch.observations.len()
+        )
+        .map_err(|e| format!("Error writing RINEX epoch: {}", e))?;
+
+        for (letter, prn, pseudorange_m, phase_cycles, cnr_dbhz) in &epoch.observations {
+            writeln!(
+                out,
+                "{}{:02}{:>14.3}{:>14.3}{:>14.3}",
+                letter, prn, pseudorange_m, phase_cycles, cnr_dbhz
+            )
+            .map_err(|e| format!("Error writing RINEX observation: {}", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes a pseudorange (metres), carrier phase (cycles) and CNR (dB-Hz)
+/// per satellite for one MSM7 epoch, picking the first unmasked signal cell
+/// per satellite.
+fn msm7_epoch_observations(
+    info: &MessageInformation,
+    letter: char,
+    num_satellites: usize,
+) -> Vec<(char, u8, f64, f64, f64)> {
+    let (satellite_mask, num_signals, cell_mask, satellites, signals) = match info {
+        MessageInformation::MSM7 {
+            satellite_mask,
+            signal_mask,
+            cell_mask,
+            satellites,
+            signals,
+            ..
+        } => (*satellite_mask, signal_mask.count_ones() as usize, cell_mask, satellites, signals),
+        _ => return Vec::new(),
+    };
+
+    let prns: Vec<u8> = (0..64u8)
+        .filter(|bit| (satellite_mask >> (63 - bit)) & 1 == 1)
+        .map(|bit| bit + 1)
+        .collect();
+
+    let first_signal_per_satellite = first_signal_per_satellite(num_satellites, num_signals, cell_mask, signals);
+
+    prns.iter()
+        .zip(satellites.iter())
+        .zip(first_signal_per_satellite.iter())
+        .filter_map(|((prn, satellite), signal)| {
+            let signal = (*signal)?;
+            // Unreachable for the 5 constellations `constellation_letter`
+            // recognises, but skip rather than mislabel if that ever changes.
+            let wavelength_m = carrier_wavelength_m(letter, satellite)?;
+            let rough_range_ms =
+                satellite.rough_range_ms as f64 + satellite.rough_range_modulo_ms as f64 / 1024.0;
+            let pseudorange_m =
+                (rough_range_ms + signal.fine_pseudorange as f64 * 2f64.powi(-29)) * SPEED_OF_LIGHT_M_PER_MS;
+            let phase_range_m =
+                (rough_range_ms + signal.fine_phase_range as f64 * 2f64.powi(-31)) * SPEED_OF_LIGHT_M_PER_MS;
+            let phase_cycles = phase_range_m / wavelength_m;
+            let cnr_dbhz = signal.cnr as f64 * 0.0625;
+
+            Some((letter, *prn, pseudorange_m, phase_cycles, cnr_dbhz))
+        })
+        .collect()
+}
+
+fn first_signal_per_satellite<'a>(
+    num_satellites: usize,
+    num_signals: usize,
+    cell_mask: &[bool],
+    signals: &'a [MSM7Signal],
+) -> Vec<Option<&'a MSM7Signal>> {
+    let mut result = Vec::with_capacity(num_satellites);
+    let mut signal_index = 0;
+
+    for satellite in 0..num_satellites {
+        let mut picked = None;
+
+        for signal in 0..num_signals {
+            if cell_mask[satellite * num_signals + signal] {
+                if picked.is_none() {
+                    picked = Some(signal_index);
+                }
+                signal_index += 1;
+            }
+        }
+
+        result.push(picked.map(|index| &signals[index]));
+    }
+
+    result
+}